@@ -0,0 +1,26 @@
+//! Low-complexity read detection via k-mer Shannon diversity, modeled on DADA2's `seqComplexity`.
+
+use std::collections::HashMap;
+
+/// Computes the effective k-mer richness of `seq`: the Shannon-diversity number
+/// `exp(-sum p_i * ln p_i)` over the frequencies `p_i` of all overlapping k-mers. A read
+/// dominated by one or two k-mers yields a value near 1-2; a complex read approaches 4^k.
+/// Reads shorter than `k` contain no k-mers and are treated as minimum complexity.
+pub fn kmer_complexity(seq: &[u8], k: usize) -> f64 {
+    if seq.len() < k || k == 0 {
+        return 1.0;
+    }
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for window in seq.windows(k) {
+        *counts.entry(window).or_insert(0) += 1;
+    }
+    let total = (seq.len() - k + 1) as f64;
+    let shannon_entropy: f64 = counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.ln()
+        })
+        .sum();
+    shannon_entropy.exp()
+}
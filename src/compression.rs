@@ -0,0 +1,46 @@
+//! Transparent (de)compression for chopper's input and output streams.
+//!
+//! Input compression is detected by sniffing the stream's magic bytes (gzip/bgzip, zstd, bzip2);
+//! output compression is selected from the `--output` path's extension (`.gz`, `.zst`).
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: [u8; 2] = [0x42, 0x5a];
+
+/// Sniffs the leading bytes of `input` and wraps it in the matching decompressing reader,
+/// falling back to the raw (buffered) stream when no known magic number is found
+pub fn wrap_reader(input: impl Read + 'static) -> io::Result<Box<dyn Read>> {
+    let mut buffered = BufReader::new(input);
+    let header = buffered.fill_buf()?;
+
+    if header.starts_with(&GZIP_MAGIC) {
+        // MultiGzDecoder transparently handles bgzip's concatenated gzip blocks too
+        Ok(Box::new(MultiGzDecoder::new(buffered)))
+    } else if header.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(zstd::stream::read::Decoder::new(buffered)?))
+    } else if header.starts_with(&BZIP2_MAGIC) {
+        Ok(Box::new(bzip2::read::BzDecoder::new(buffered)))
+    } else {
+        Ok(Box::new(buffered))
+    }
+}
+
+/// Opens `path` for writing, wrapping it in a compressing writer selected from its extension
+/// (`.gz` for gzip, `.zst` for zstd) at the given `level`, or a plain buffered writer otherwise
+pub fn writer_for_path(path: &str, level: u32) -> io::Result<Box<dyn Write + Send>> {
+    let file = File::create(path)?;
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => Ok(Box::new(GzEncoder::new(file, Compression::new(level)))),
+        Some("zst") => Ok(Box::new(
+            zstd::stream::write::Encoder::new(file, level as i32)?.auto_finish(),
+        )),
+        _ => Ok(Box::new(BufWriter::new(file))),
+    }
+}
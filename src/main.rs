@@ -1,12 +1,19 @@
 // based on https://docs.rs/bio/0.32.0/bio/io/fastq/index.html#read-and-write
+mod compression;
+mod complexity;
+mod primer;
+mod stats;
+mod subsample;
+
 use bio::io::fastq;
 use clap::AppSettings::DeriveDisplayOrder;
 use clap::Parser;
 use minimap2::*;
 use rayon::prelude::*;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 // The arguments end up in the Cli struct
 #[derive(Parser, Debug)]
@@ -17,6 +24,10 @@ struct Cli {
     #[clap(short = 'q', long = "quality", value_parser, default_value_t = 0.0)]
     minqual: f64,
 
+    /// Sets the maximum number of expected errors (DADA2/vsearch-style maxEE filter)
+    #[clap(long, value_parser, default_value_t = f64::INFINITY)]
+    maxee: f64,
+
     /// Sets a minimum read length
     #[clap(short = 'l', long, value_parser, default_value_t = 1)]
     minlength: usize,
@@ -34,6 +45,15 @@ struct Cli {
     #[clap(long, value_parser, default_value_t = 0)]
     tailcrop: usize,
 
+    /// Truncate a read at the first base, scanning from the 5' end, whose Phred score is at or below Q
+    #[clap(long, value_parser)]
+    truncqual: Option<u8>,
+
+    /// Trimmomatic-style sliding window trim, formatted as SIZE:Q: cut the read where the average
+    /// quality of a window of SIZE bases first falls below Q
+    #[clap(long, value_parser)]
+    window: Option<Window>,
+
     /// Use N parallel threads
     #[clap(short, long, value_parser, default_value_t = 4)]
     threads: usize,
@@ -41,6 +61,106 @@ struct Cli {
     /// Filter contaminants against a fasta
     #[clap(short, long, validator = is_file)]
     contam: Option<String>,
+
+    /// Path to the first mate of a paired-end input, enabling paired mode (requires --input2/--output1/--output2)
+    #[clap(long, value_parser, requires_all = &["input2", "output1", "output2"])]
+    input1: Option<String>,
+
+    /// Path to the second mate of a paired-end input (requires --input1/--output1/--output2)
+    #[clap(long, value_parser, requires_all = &["input1", "output1", "output2"])]
+    input2: Option<String>,
+
+    /// Output path for the filtered first mate (paired mode)
+    #[clap(long, value_parser, requires = "input1")]
+    output1: Option<String>,
+
+    /// Output path for the filtered second mate (paired mode)
+    #[clap(long, value_parser, requires = "input2")]
+    output2: Option<String>,
+
+    /// Write output to this path instead of stdout; the extension (.gz, .zst) selects the output codec
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+
+    /// Compression level to use when --output (or --output1/--output2) is compressed
+    #[clap(long, value_parser, default_value_t = 6)]
+    compress_level: u32,
+
+    /// Forward primer sequence (IUPAC ambiguity codes allowed); located near the 5' end and
+    /// trimmed along with everything before it
+    #[clap(long, value_parser)]
+    primer_fwd: Option<String>,
+
+    /// Reverse primer sequence (IUPAC ambiguity codes allowed); its reverse complement is
+    /// located near the 3' end and trimmed along with everything after it
+    #[clap(long, value_parser)]
+    primer_rev: Option<String>,
+
+    /// Number of mismatches allowed when locating a primer
+    #[clap(long, value_parser, default_value_t = 0)]
+    primer_mismatches: usize,
+
+    /// Discard reads in which a requested primer could not be found, instead of leaving them untrimmed
+    #[clap(long)]
+    discard_untrimmed: bool,
+
+    /// Minimum k-mer Shannon-diversity complexity score required to keep a read
+    #[clap(long, value_parser, default_value_t = 0.0)]
+    min_complexity: f64,
+
+    /// k-mer size used to compute the complexity score
+    #[clap(long, value_parser, default_value_t = 2)]
+    complexity_k: usize,
+
+    /// Genome size for coverage-based subsampling, e.g. "4.2mb" or "3g" (requires --coverage);
+    /// enables subsampling mode, composed after the quality/length filters
+    #[clap(long, value_parser, requires = "coverage")]
+    genome_size: Option<String>,
+
+    /// Target depth of coverage to subsample down to (requires --genome-size)
+    #[clap(long, value_parser, requires = "genome_size")]
+    coverage: Option<f64>,
+
+    /// Keep each read independently with this probability; enables subsampling mode
+    #[clap(long, value_parser)]
+    fraction: Option<f64>,
+
+    /// Keep exactly this many reads (reservoir sampling); enables subsampling mode
+    #[clap(long, value_parser)]
+    num_reads: Option<usize>,
+
+    /// Seed for the subsampling random number generator, for reproducible output
+    #[clap(long, value_parser, default_value_t = 1)]
+    seed: u64,
+
+    /// Write a before/after QC summary to this path (JSON, or TSV if the extension is .tsv)
+    #[clap(long, value_parser)]
+    stats: Option<String>,
+}
+
+/// A Trimmomatic-style `SIZE:Q` sliding window specification
+#[derive(Debug, Clone)]
+struct Window {
+    size: usize,
+    qual: f64,
+}
+
+impl std::str::FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (size, qual) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid window {}, expected SIZE:Q", s))?;
+        Ok(Window {
+            size: size
+                .parse()
+                .map_err(|e| format!("Invalid window size {}: {}", size, e))?,
+            qual: qual
+                .parse()
+                .map_err(|e| format!("Invalid window quality {}: {}", qual, e))?,
+        })
+    }
 }
 
 fn is_file(pathname: &str) -> Result<(), String> {
@@ -54,51 +174,98 @@ fn is_file(pathname: &str) -> Result<(), String> {
 
 fn main() {
     let args = Cli::parse();
-    filter(&mut io::stdin(), args);
+    if args.num_reads.is_some() || args.fraction.is_some() || args.genome_size.is_some() || args.coverage.is_some() {
+        subsample(io::stdin(), args);
+    } else if args.input1.is_some() {
+        filter_paired(&args);
+    } else {
+        filter(io::stdin(), args);
+    }
+}
+
+/// Builds the subsampling [`subsample::Target`] requested on the command line
+fn subsample_target(args: &Cli) -> subsample::Target {
+    if let Some(n) = args.num_reads {
+        subsample::Target::NumReads(n)
+    } else if let Some(f) = args.fraction {
+        subsample::Target::Fraction(f)
+    } else {
+        let genome_size = args
+            .genome_size
+            .as_ref()
+            .expect("--genome-size is required for coverage-based subsampling");
+        let coverage = args
+            .coverage
+            .expect("--coverage is required for coverage-based subsampling");
+        let bases = subsample::parse_genome_size(genome_size).expect("Invalid --genome-size");
+        subsample::Target::Bases((bases as f64 * coverage).round() as u64)
+    }
+}
+
+/// Runs the quality/length filters over `input` and offers each surviving read to a reservoir
+/// that subsamples them down to the requested coverage/fraction/count, all in one pass over the
+/// input; the coverage target buffers every surviving read and draws from them once it ends
+fn subsample(input: impl Read + 'static, args: Cli) {
+    let reader = compression::wrap_reader(input).expect("Unable to read input");
+    let aligner = args.contam.as_ref().map(|fas| setup_contamination_filter(fas));
+    let mut reservoir = subsample::Reservoir::new(subsample_target(&args), args.seed);
+    let mut stats = stats::StatsAccumulator::new();
+
+    fastq::Reader::new(reader)
+        .records()
+        .into_iter()
+        .for_each(|record| {
+            let record = record.unwrap();
+            stats.record_input(record.seq().len());
+            if let Some((out, trimmed_len, mean_qual)) = filter_record(&record, &args, aligner.as_ref()) {
+                reservoir.offer((out, trimmed_len, mean_qual), trimmed_len as u64);
+            }
+        });
+
+    let mut writer: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            compression::writer_for_path(path, args.compress_level).expect("Unable to open --output")
+        }
+        None => Box::new(io::BufWriter::new(io::stdout())),
+    };
+    // Stats reflect the reads actually emitted after subsampling, not just the filter pass
+    for (out, trimmed_len, mean_qual) in reservoir.into_items() {
+        stats.record_output(trimmed_len, mean_qual);
+        writeln!(writer, "{}", out).expect("Unable to write output");
+    }
+
+    if let Some(path) = &args.stats {
+        stats.write_report(path).expect("Unable to write --stats");
+    }
 }
 
 /// This function filters fastq on stdin based on quality, maxlength and minlength
 /// and applies trimming before writting to stdout
-fn filter(input: &mut impl Read, args: Cli) {
+/// (or to the `--output` path, when one is given)
+fn filter(input: impl Read + 'static, args: Cli) {
+    let reader = compression::wrap_reader(input).expect("Unable to read input");
+    let writer: Box<dyn Write + Send> = match &args.output {
+        Some(path) => compression::writer_for_path(path, args.compress_level)
+            .expect("Unable to open --output"),
+        None => Box::new(io::BufWriter::new(io::stdout())),
+    };
+    let writer = Mutex::new(writer);
+    let stats = Mutex::new(stats::StatsAccumulator::new());
+
     match args.contam {
         Some(ref fas) => {
             let aligner = setup_contamination_filter(fas);
-            fastq::Reader::new(input)
+            fastq::Reader::new(reader)
                 .records()
                 .into_iter()
                 .for_each(|record| {
                     let record = record.unwrap();
-                    if !record.is_empty() {
-                        let read_len = record.seq().len();
-                        // If a read is shorter than what is to be cropped the read is dropped entirely (filtered out)
-                        if args.headcrop + args.tailcrop < read_len {
-                            let average_quality = ave_qual(record.qual());
-                            if average_quality >= args.minqual
-                                && read_len >= args.minlength
-                                && read_len <= args.maxlength
-                                && !is_contamination(&record.seq(), &aligner)
-                            {
-                                // Check if a description attribute is present, taken from the bio-rust code to format fastq
-                                let header = match record.desc() {
-                                    Some(d) => format!("{} {}", record.id(), d),
-                                    None => record.id().to_owned(),
-                                };
-                                // Print out the records passing the filters, applying trimming on seq and qual
-                                // Could consider to use unsafe `from_utf8_unchecked`
-                                println!(
-                                    "@{}\n{}\n+\n{}",
-                                    header,
-                                    std::str::from_utf8(
-                                        &record.seq()[args.headcrop..read_len - args.tailcrop]
-                                    )
-                                    .unwrap(),
-                                    std::str::from_utf8(
-                                        &record.qual()[args.headcrop..read_len - args.tailcrop]
-                                    )
-                                    .unwrap()
-                                );
-                            }
-                        }
+                    stats.lock().unwrap().record_input(record.seq().len());
+                    if let Some((out, trimmed_len, mean_qual)) =
+                        filter_record(&record, &args, Some(&aligner))
+                    {
+                        stats.lock().unwrap().record_output(trimmed_len, mean_qual);
+                        writeln!(writer.lock().unwrap(), "{}", out).expect("Unable to write output");
                     }
                 });
         }
@@ -108,57 +275,206 @@ fn filter(input: &mut impl Read, args: Cli) {
                 .num_threads(args.threads)
                 .build()
                 .unwrap();
-            fastq::Reader::new(io::stdin())
+            fastq::Reader::new(reader)
                 .records()
                 .into_iter()
                 .par_bridge()
                 .for_each(|record| {
                     let record = record.unwrap();
-                    if !record.is_empty() {
-                        let read_len = record.seq().len();
-                        // If a read is shorter than what is to be cropped the read is dropped entirely (filtered out)
-                        if args.headcrop + args.tailcrop < read_len {
-                            let average_quality = ave_qual(record.qual());
-                            if average_quality >= args.minqual
-                                && read_len >= args.minlength
-                                && read_len <= args.maxlength
-                            {
-                                // Check if a description attribute is present, taken from the bio-rust code to format fastq
-                                let header = match record.desc() {
-                                    Some(d) => format!("{} {}", record.id(), d),
-                                    None => record.id().to_owned(),
-                                };
-                                // Print out the records passing the filters, applying trimming on seq and qual
-                                // Could consider to use unsafe `from_utf8_unchecked`
-                                println!(
-                                    "@{}\n{}\n+\n{}",
-                                    header,
-                                    std::str::from_utf8(
-                                        &record.seq()[args.headcrop..read_len - args.tailcrop]
-                                    )
-                                    .unwrap(),
-                                    std::str::from_utf8(
-                                        &record.qual()[args.headcrop..read_len - args.tailcrop]
-                                    )
-                                    .unwrap()
-                                );
-                            }
-                        }
+                    stats.lock().unwrap().record_input(record.seq().len());
+                    if let Some((out, trimmed_len, mean_qual)) = filter_record(&record, &args, None) {
+                        stats.lock().unwrap().record_output(trimmed_len, mean_qual);
+                        writeln!(writer.lock().unwrap(), "{}", out).expect("Unable to write output");
                     }
                 });
         }
     }
+
+    if let Some(path) = &args.stats {
+        stats
+            .into_inner()
+            .unwrap()
+            .write_report(path)
+            .expect("Unable to write --stats");
+    }
+}
+
+/// Filters a paired-end fastq input (`--input1`/`--input2`) in lockstep, keeping the outputs
+/// synchronized: a pair is written to `--output1`/`--output2` only if both mates pass the
+/// filters, since either mate failing would otherwise desynchronize the two files
+fn filter_paired(args: &Cli) {
+    let input1 = args.input1.as_ref().expect("--input1 is required in paired mode");
+    let input2 = args.input2.as_ref().expect("--input2 is required in paired mode");
+    let output1 = args.output1.as_ref().expect("--output1 is required in paired mode");
+    let output2 = args.output2.as_ref().expect("--output2 is required in paired mode");
+
+    let aligner = args.contam.as_ref().map(|fas| setup_contamination_filter(fas));
+
+    let reader1 = compression::wrap_reader(File::open(input1).expect("Unable to open --input1"))
+        .expect("Unable to read --input1");
+    let reader2 = compression::wrap_reader(File::open(input2).expect("Unable to open --input2"))
+        .expect("Unable to read --input2");
+    let reader1 = fastq::Reader::new(reader1);
+    let reader2 = fastq::Reader::new(reader2);
+    let mut writer1 = compression::writer_for_path(output1, args.compress_level)
+        .expect("Unable to create --output1");
+    let mut writer2 = compression::writer_for_path(output2, args.compress_level)
+        .expect("Unable to create --output2");
+    let mut stats = stats::StatsAccumulator::new();
+
+    reader1
+        .records()
+        .zip(reader2.records())
+        .for_each(|(record1, record2)| {
+            let record1 = record1.unwrap();
+            let record2 = record2.unwrap();
+            stats.record_input(record1.seq().len());
+            stats.record_input(record2.seq().len());
+            // Trimming is applied per-mate independently, but the pair is only kept if both
+            // mates pass the quality/length/contamination filters, to keep the outputs in sync
+            let mate1 = filter_record(&record1, args, aligner.as_ref());
+            let mate2 = filter_record(&record2, args, aligner.as_ref());
+            if let (Some((mate1, len1, qual1)), Some((mate2, len2, qual2))) = (mate1, mate2) {
+                stats.record_output(len1, qual1);
+                stats.record_output(len2, qual2);
+                writeln!(writer1, "{}", mate1).expect("Unable to write --output1");
+                writeln!(writer2, "{}", mate2).expect("Unable to write --output2");
+            }
+        });
+
+    if let Some(path) = &args.stats {
+        stats.write_report(path).expect("Unable to write --stats");
+    }
+}
+
+/// Applies cropping/quality trimming and the quality/length/contamination filters to a single
+/// record, returning the formatted fastq record plus its trimmed length and mean quality if it
+/// passes, or `None` if it's filtered out
+fn filter_record(
+    record: &fastq::Record,
+    args: &Cli,
+    aligner: Option<&Aligner>,
+) -> Option<(String, usize, f64)> {
+    if record.is_empty() {
+        return None;
+    }
+    let read_len = record.seq().len();
+    // If a read is shorter than what is to be cropped the read is dropped entirely (filtered out)
+    if args.headcrop + args.tailcrop >= read_len {
+        return None;
+    }
+    let crop_start = args.headcrop;
+    let crop_end = read_len - args.tailcrop;
+    let mut start = crop_start;
+    let mut end =
+        crop_start + quality_trim(&record.qual()[crop_start..crop_end], args.truncqual, args.window.as_ref());
+
+    if let Some(primer) = &args.primer_fwd {
+        match primer::find_fwd_primer(&record.seq()[start..end], primer.as_bytes(), args.primer_mismatches) {
+            Some(cut) => start += cut,
+            None if args.discard_untrimmed => return None,
+            None => {}
+        }
+    }
+    if let Some(primer) = &args.primer_rev {
+        match primer::find_rev_primer(&record.seq()[start..end], primer.as_bytes(), args.primer_mismatches) {
+            Some(cut) => end = start + cut,
+            None if args.discard_untrimmed => return None,
+            None => {}
+        }
+    }
+
+    let seq = &record.seq()[start..end];
+    let qual = &record.qual()[start..end];
+    let trimmed_len = seq.len();
+
+    // Computed once and reused for both the expected-error count and the average quality,
+    // rather than summing the per-base error probabilities twice
+    let error_sum = sum_error_probability(qual);
+    let average_quality = error_sum_to_ave_qual(error_sum, qual.len());
+    let expected_errors = error_sum;
+    // Skip the per-record k-mer HashMap allocation when the complexity filter is off (its default)
+    let complexity_ok =
+        args.min_complexity <= 0.0 || complexity::kmer_complexity(seq, args.complexity_k) >= args.min_complexity;
+
+    if average_quality >= args.minqual
+        && expected_errors <= args.maxee
+        && trimmed_len >= args.minlength
+        && trimmed_len <= args.maxlength
+        && complexity_ok
+        && aligner.map_or(true, |aligner| !is_contamination(&seq, aligner))
+    {
+        // Check if a description attribute is present, taken from the bio-rust code to format fastq
+        let header = match record.desc() {
+            Some(d) => format!("{} {}", record.id(), d),
+            None => record.id().to_owned(),
+        };
+        // Could consider to use unsafe `from_utf8_unchecked`
+        let formatted = format!(
+            "@{}\n{}\n+\n{}",
+            header,
+            std::str::from_utf8(seq).unwrap(),
+            std::str::from_utf8(qual).unwrap()
+        );
+        Some((formatted, trimmed_len, average_quality))
+    } else {
+        None
+    }
+}
+
+/// Applies `--truncqual` and `--window` trimming to a (already head/tail-cropped) quality string
+/// and returns the length to keep, counted from the start
+fn quality_trim(qual: &[u8], truncqual: Option<u8>, window: Option<&Window>) -> usize {
+    let mut end = qual.len();
+    if let Some(q) = truncqual {
+        end = end.min(truncqual_trim(&qual[..end], q));
+    }
+    if let Some(w) = window {
+        end = end.min(window_trim(&qual[..end], w.size, w.qual));
+    }
+    end
+}
+
+/// Scans `qual` from the 5' end and returns the length to keep up to (but excluding) the first,
+/// left-most base whose Phred score drops to/below `truncqual` (DADA2's `truncQ` behavior)
+fn truncqual_trim(qual: &[u8], truncqual: u8) -> usize {
+    qual.iter().position(|&q| q <= truncqual).unwrap_or(qual.len())
+}
+
+/// Slides a window of `size` bases across `qual` and returns the length to keep up to the start
+/// of the first window whose average quality drops below `min_qual` (Trimmomatic's `SLIDINGWINDOW`)
+fn window_trim(qual: &[u8], size: usize, min_qual: f64) -> usize {
+    if size == 0 || size > qual.len() {
+        return qual.len();
+    }
+    for start in 0..=(qual.len() - size) {
+        if ave_qual(&qual[start..start + size]) < min_qual {
+            return start;
+        }
+    }
+    qual.len()
+}
+
+/// Sums the per-base error probabilities (10^(-Q/10)) of a quality string
+/// This is the basis for both the average Phred quality and the expected-error count
+fn sum_error_probability(quals: &[u8]) -> f64 {
+    quals
+        .iter()
+        .map(|q| 10_f64.powf((*q as f64) / -10.0))
+        .sum::<f64>()
+}
+
+/// Converts an already-summed per-base error probability (see `sum_error_probability`) back to
+/// a Phred-scale average quality, by dividing by the number of bases and -10*log10'ing it
+fn error_sum_to_ave_qual(error_sum: f64, num_bases: usize) -> f64 {
+    (error_sum / num_bases as f64).log10() * -10.0
 }
 
 /// This function calculates the average quality of a read, and does this correctly
 /// First the Phred scores are converted to probabilities (10^(q)/-10) and summed
 /// and then divided by the number of bases/scores and converted to Phred again -10*log10(average)
 fn ave_qual(quals: &[u8]) -> f64 {
-    let probability_sum = quals
-        .iter()
-        .map(|q| 10_f64.powf((*q as f64) / -10.0))
-        .sum::<f64>();
-    (probability_sum / quals.len() as f64).log10() * -10.0
+    error_sum_to_ave_qual(sum_error_probability(quals), quals.len())
 }
 
 fn setup_contamination_filter(contam_fasta: &str) -> Aligner {
@@ -191,18 +507,125 @@ fn test_ave_qual() {
 #[test]
 fn test_filter() {
     filter(
-        &mut File::open("test-data/test.fastq").unwrap(),
+        File::open("test-data/test.fastq").unwrap(),
         Cli {
             minlength: 100,
             maxlength: 100000,
             minqual: 5.0,
+            maxee: f64::INFINITY,
             headcrop: 10,
             tailcrop: 10,
+            truncqual: None,
+            window: None,
             threads: 2,
             contam: None,
+            input1: None,
+            input2: None,
+            output1: None,
+            output2: None,
+            output: None,
+            compress_level: 6,
+            primer_fwd: None,
+            primer_rev: None,
+            primer_mismatches: 0,
+            discard_untrimmed: false,
+            min_complexity: 0.0,
+            complexity_k: 2,
+            genome_size: None,
+            coverage: None,
+            fraction: None,
+            num_reads: None,
+            seed: 1,
+            stats: None,
         },
     );
 }
+
+#[test]
+fn test_kmer_complexity() {
+    // A homopolymer has a single 2-mer, so its richness is the minimum value of 1.0
+    assert_eq!(complexity::kmer_complexity(b"AAAAAA", 2), 1.0);
+    // A read shorter than k has no k-mers and is treated as minimum complexity too
+    assert_eq!(complexity::kmer_complexity(b"A", 2), 1.0);
+    // A read cycling evenly through 4 distinct 2-mers has a richness near 4, far above a
+    // homopolymer's 1.0
+    assert!(complexity::kmer_complexity(b"ACGTACGTACGTACGTACGT", 2) > 3.5);
+}
+
+#[test]
+fn test_parse_genome_size() {
+    assert_eq!(subsample::parse_genome_size("3g").unwrap(), 3_000_000_000);
+    assert_eq!(subsample::parse_genome_size("4.2mb").unwrap(), 4_200_000);
+    assert_eq!(subsample::parse_genome_size("500k").unwrap(), 500_000);
+    assert_eq!(subsample::parse_genome_size("42").unwrap(), 42);
+}
+
+#[test]
+fn test_reservoir_num_reads() {
+    let mut reservoir = subsample::Reservoir::new(subsample::Target::NumReads(2), 42);
+    for i in 0..10 {
+        reservoir.offer(i, 1);
+    }
+    assert_eq!(reservoir.into_items().len(), 2);
+}
+
+#[test]
+fn test_stats_accumulator() {
+    let mut accumulator = stats::StatsAccumulator::new();
+    accumulator.record_input(100);
+    accumulator.record_input(200);
+    accumulator.record_output(100, 20.0);
+    let report = accumulator.to_json();
+    assert!(report.contains("\"reads_in\": 2"));
+    assert!(report.contains("\"reads_out\": 1"));
+    assert!(report.contains("\"n50\": 100"));
+}
+
+#[test]
+fn test_find_fwd_primer() {
+    // "N" matches any base, so the primer is found (with 0 mismatches) right at the start
+    assert_eq!(primer::find_fwd_primer(b"ACGTACGT", b"ACNT", 0), Some(4));
+    assert_eq!(primer::find_fwd_primer(b"ACGTACGT", b"TTTT", 0), None);
+}
+
+#[test]
+fn test_find_rev_primer() {
+    // The reverse complement of "ACGT" is "ACGT", found at the very end of the read
+    assert_eq!(primer::find_rev_primer(b"TTTTACGT", b"ACGT", 0), Some(4));
+}
+
+#[test]
+fn test_reverse_complement_ambiguity_codes() {
+    // R (A/G) complements to Y (C/T), not to itself
+    assert_eq!(primer::reverse_complement(b"R"), b"Y");
+    // 806R-style primer with N/V/W ambiguity codes
+    assert_eq!(
+        primer::reverse_complement(b"GGACTACNVGGGTWTCTAAT"),
+        b"ATTAGAWACCCBNGTAGTCC"
+    );
+}
+
+#[test]
+fn test_maxee() {
+    // A single Q10 base has an expected error count of 10^(-10/10) = 0.1
+    assert!(approx::abs_diff_eq!(sum_error_probability(&[10]), 0.1));
+}
+
+#[test]
+fn test_truncqual_trim() {
+    assert_eq!(truncqual_trim(&[30, 30, 30, 5, 30], 10), 3);
+    assert_eq!(truncqual_trim(&[30, 30, 30, 30], 10), 4);
+    // An interior low-quality base truncates there, even though a later (3') base is fine
+    assert_eq!(truncqual_trim(&[30, 5, 30, 5, 30], 10), 1);
+}
+
+#[test]
+fn test_window_trim() {
+    // The window starting at index 2 (quals 30,5) already averages about Q8 in probability
+    // space, well below 20, so the read is cut before it
+    assert_eq!(window_trim(&[30, 30, 30, 5, 5], 2, 20.0), 2);
+    assert_eq!(window_trim(&[30, 30, 30, 30], 2, 20.0), 4);
+}
 // FEATURES TO ADD
 // Write test for ave_qual
 // write integration tests
@@ -0,0 +1,86 @@
+//! IUPAC-aware primer matching and trimming, modeled on DADA2's `removePrimers`.
+
+/// Returns the set of bases an IUPAC ambiguity code matches
+fn iupac_bases(code: u8) -> &'static [u8] {
+    match code.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"GC",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => b"",
+    }
+}
+
+fn iupac_match(code: u8, base: u8) -> bool {
+    iupac_bases(code).contains(&base.to_ascii_uppercase())
+}
+
+fn complement_base(base: u8) -> u8 {
+    match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' | b'U' => b'A',
+        // Ambiguity codes complement to the code for the complementary set of bases
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        // W (A/T), S (G/C) and N (A/C/G/T) are each their own complement
+        other => other,
+    }
+}
+
+/// Reverse-complements a sequence, complementing IUPAC ambiguity codes to the code for their
+/// complementary base set (e.g. R <-> Y) rather than leaving them untouched
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().map(|&b| complement_base(b)).collect()
+}
+
+fn count_mismatches(primer: &[u8], window: &[u8]) -> usize {
+    primer
+        .iter()
+        .zip(window.iter())
+        .filter(|(&p, &b)| !iupac_match(p, b))
+        .count()
+}
+
+/// Slides `primer` across the start of `seq`, left to right, and returns the index right after
+/// the first window within `max_mismatches`, i.e. the point at which to trim the primer and
+/// everything 5' of it
+pub fn find_fwd_primer(seq: &[u8], primer: &[u8], max_mismatches: usize) -> Option<usize> {
+    if primer.len() > seq.len() {
+        return None;
+    }
+    (0..=seq.len() - primer.len())
+        .find(|&start| count_mismatches(primer, &seq[start..start + primer.len()]) <= max_mismatches)
+        .map(|start| start + primer.len())
+}
+
+/// Slides the reverse complement of `primer` across the end of `seq`, right to left, and returns
+/// the index of the right-most window within `max_mismatches`, i.e. the point at which to trim
+/// the primer and everything 3' of it
+pub fn find_rev_primer(seq: &[u8], primer: &[u8], max_mismatches: usize) -> Option<usize> {
+    let revcomp = reverse_complement(primer);
+    if revcomp.len() > seq.len() {
+        return None;
+    }
+    (0..=seq.len() - revcomp.len())
+        .rev()
+        .find(|&start| count_mismatches(&revcomp, &seq[start..start + revcomp.len()]) <= max_mismatches)
+}
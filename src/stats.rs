@@ -0,0 +1,160 @@
+//! Accumulates before/after QC summary statistics for `--stats` and writes them as JSON or TSV.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::{fs, io};
+
+const LENGTH_BIN_WIDTH: usize = 100;
+const QUALITY_BIN_WIDTH: i64 = 1;
+
+#[derive(Default)]
+pub struct StatsAccumulator {
+    reads_in: u64,
+    bases_in: u64,
+    reads_out: u64,
+    bases_out: u64,
+    out_lengths: Vec<usize>,
+    out_quals: Vec<f64>,
+}
+
+impl StatsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a read as it was seen on input, before any filtering or trimming
+    pub fn record_input(&mut self, read_len: usize) {
+        self.reads_in += 1;
+        self.bases_in += read_len as u64;
+    }
+
+    /// Records a read that survived filtering, using its final (trimmed) length and mean quality
+    pub fn record_output(&mut self, read_len: usize, mean_qual: f64) {
+        self.reads_out += 1;
+        self.bases_out += read_len as u64;
+        self.out_lengths.push(read_len);
+        self.out_quals.push(mean_qual);
+    }
+
+    fn n50(&self) -> usize {
+        let mut lengths = self.out_lengths.clone();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        let half_bases = self.bases_out / 2;
+        let mut cumulative = 0u64;
+        for &len in &lengths {
+            cumulative += len as u64;
+            if cumulative >= half_bases {
+                return len;
+            }
+        }
+        lengths.last().copied().unwrap_or(0)
+    }
+
+    fn mean_length(&self) -> f64 {
+        if self.out_lengths.is_empty() {
+            0.0
+        } else {
+            self.bases_out as f64 / self.out_lengths.len() as f64
+        }
+    }
+
+    fn median_length(&self) -> f64 {
+        if self.out_lengths.is_empty() {
+            return 0.0;
+        }
+        let mut lengths = self.out_lengths.clone();
+        lengths.sort_unstable();
+        let mid = lengths.len() / 2;
+        if lengths.len() % 2 == 0 {
+            (lengths[mid - 1] + lengths[mid]) as f64 / 2.0
+        } else {
+            lengths[mid] as f64
+        }
+    }
+
+    fn mean_quality(&self) -> f64 {
+        if self.out_quals.is_empty() {
+            0.0
+        } else {
+            self.out_quals.iter().sum::<f64>() / self.out_quals.len() as f64
+        }
+    }
+
+    fn length_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for &len in &self.out_lengths {
+            *histogram
+                .entry((len / LENGTH_BIN_WIDTH) * LENGTH_BIN_WIDTH)
+                .or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    fn quality_histogram(&self) -> BTreeMap<i64, usize> {
+        let mut histogram = BTreeMap::new();
+        for &qual in &self.out_quals {
+            let bin = (qual / QUALITY_BIN_WIDTH as f64).floor() as i64 * QUALITY_BIN_WIDTH;
+            *histogram.entry(bin).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Writes the accumulated summary to `path`: TSV if its extension is `.tsv`, JSON otherwise
+    pub fn write_report(&self, path: &str) -> io::Result<()> {
+        let report = if path.ends_with(".tsv") {
+            self.to_tsv()
+        } else {
+            self.to_json()
+        };
+        fs::write(path, report)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        let mut length_histogram = String::new();
+        for (bin, count) in self.length_histogram() {
+            let _ = write!(length_histogram, "\"{}\": {},", bin, count);
+        }
+        length_histogram.pop();
+
+        let mut quality_histogram = String::new();
+        for (bin, count) in self.quality_histogram() {
+            let _ = write!(quality_histogram, "\"{}\": {},", bin, count);
+        }
+        quality_histogram.pop();
+
+        format!(
+            "{{\n  \"reads_in\": {},\n  \"bases_in\": {},\n  \"reads_out\": {},\n  \"bases_out\": {},\n  \"n50\": {},\n  \"mean_length\": {:.2},\n  \"median_length\": {:.2},\n  \"mean_quality\": {:.2},\n  \"length_histogram\": {{{}}},\n  \"quality_histogram\": {{{}}}\n}}\n",
+            self.reads_in,
+            self.bases_in,
+            self.reads_out,
+            self.bases_out,
+            self.n50(),
+            self.mean_length(),
+            self.median_length(),
+            self.mean_quality(),
+            length_histogram,
+            quality_histogram,
+        )
+    }
+
+    fn to_tsv(&self) -> String {
+        let mut lines = vec![
+            "metric\tvalue".to_string(),
+            format!("reads_in\t{}", self.reads_in),
+            format!("bases_in\t{}", self.bases_in),
+            format!("reads_out\t{}", self.reads_out),
+            format!("bases_out\t{}", self.bases_out),
+            format!("n50\t{}", self.n50()),
+            format!("mean_length\t{:.2}", self.mean_length()),
+            format!("median_length\t{:.2}", self.median_length()),
+            format!("mean_quality\t{:.2}", self.mean_quality()),
+        ];
+        for (bin, count) in self.length_histogram() {
+            lines.push(format!("length_histogram[{}]\t{}", bin, count));
+        }
+        for (bin, count) in self.quality_histogram() {
+            lines.push(format!("quality_histogram[{}]\t{}", bin, count));
+        }
+        lines.join("\n") + "\n"
+    }
+}
@@ -0,0 +1,116 @@
+//! Seeded random subsampling of reads to a target depth of coverage, inspired by rasusa. The
+//! read-count and per-read-probability targets stream through a small reservoir; the base-budget
+//! target needs every candidate read to pick an unbiased whole-read subset, so it buffers
+//! offered reads and draws from them once the stream ends (see [`Target::Bases`]).
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// What a [`Reservoir`] is sampling towards
+pub enum Target {
+    /// Keep a uniformly random subset of whole reads totalling approximately this many bases:
+    /// offered reads are shuffled into a random order, then taken in that order until the base
+    /// budget is met (see [`Reservoir::into_items`])
+    Bases(u64),
+    /// Keep each read independently with this probability
+    Fraction(f64),
+    /// Keep exactly this many reads
+    NumReads(usize),
+}
+
+/// Parses a genome size such as `4.2mb` or `3g` into a number of bases
+pub fn parse_genome_size(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let (digits, multiplier) = if let Some(n) = lower.strip_suffix("gb").or_else(|| lower.strip_suffix('g')) {
+        (n, 1_000_000_000.0)
+    } else if let Some(n) = lower.strip_suffix("mb").or_else(|| lower.strip_suffix('m')) {
+        (n, 1_000_000.0)
+    } else if let Some(n) = lower.strip_suffix("kb").or_else(|| lower.strip_suffix('k')) {
+        (n, 1_000.0)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+    digits
+        .trim()
+        .parse::<f64>()
+        .map(|n| (n * multiplier).round() as u64)
+        .map_err(|e| format!("Invalid genome size '{}': {}", s, e))
+}
+
+/// A seeded reservoir that keeps a randomly selected subset of the items offered to it,
+/// towards a [`Target`]
+pub struct Reservoir<T> {
+    target: Target,
+    rng: StdRng,
+    // Each offered item alongside its length in bases
+    items: Vec<(u64, T)>,
+    seen: usize,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(target: Target, seed: u64) -> Self {
+        Reservoir {
+            target,
+            rng: StdRng::seed_from_u64(seed),
+            items: Vec::new(),
+            seen: 0,
+        }
+    }
+
+    /// Offers `item` (of length `len` bases) to the reservoir, keeping, swapping in, or
+    /// buffering it depending on the target strategy
+    pub fn offer(&mut self, item: T, len: u64) {
+        match self.target {
+            Target::Fraction(p) => {
+                if self.rng.gen::<f64>() < p {
+                    self.items.push((len, item));
+                }
+            }
+            // Classic reservoir sampling (Algorithm R): the i-th item is kept with
+            // probability n/i, replacing a uniformly random slot
+            Target::NumReads(n) => {
+                self.seen += 1;
+                if self.items.len() < n {
+                    self.items.push((len, item));
+                } else {
+                    let j = self.rng.gen_range(0..self.seen);
+                    if j < n {
+                        self.items[j] = (len, item);
+                    }
+                }
+            }
+            // Every offered read is buffered; the base budget is drawn from the full candidate
+            // set at the end, in `into_items`, since which reads to keep can't be decided
+            // correctly until all of them (and their order) are known
+            Target::Bases(_) => {
+                self.items.push((len, item));
+            }
+        }
+    }
+
+    /// Consumes the reservoir, returning the kept items. For [`Target::Bases`], every offered
+    /// read is shuffled into a uniformly random order (Fisher-Yates) and then taken in that
+    /// order until the base budget is met, so every same-sized subset of whole reads is equally
+    /// likely, unlike evicting at random as reads arrive (which is biased towards later reads)
+    pub fn into_items(mut self) -> Vec<T> {
+        if let Target::Bases(budget) = self.target {
+            for i in (1..self.items.len()).rev() {
+                self.items.swap(i, self.rng.gen_range(0..=i));
+            }
+            let mut total_bases = 0u64;
+            return self
+                .items
+                .into_iter()
+                .take_while(|(len, _)| {
+                    if total_bases >= budget {
+                        return false;
+                    }
+                    total_bases += len;
+                    true
+                })
+                .map(|(_, item)| item)
+                .collect();
+        }
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}